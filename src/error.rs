@@ -0,0 +1,62 @@
+use failure::{Backtrace, Context, Fail};
+use std::fmt::{self, Display};
+
+/// A specialized [`Result`](std::result::Result) type for this crate's operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error that can occur while parsing or negotiating encodings.
+#[derive(Debug)]
+pub struct Error {
+    inner: Context<ErrorKind>,
+}
+
+/// A set of errors that can occur while parsing or negotiating encodings.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Fail)]
+pub enum ErrorKind {
+    /// The encoding could not be parsed.
+    #[fail(display = "The encoding is not valid.")]
+    InvalidEncoding,
+    /// The encoding is not a known content coding.
+    #[fail(display = "The encoding scheme is unknown.")]
+    UnknownEncoding,
+    /// None of the client's acceptable encodings are supported.
+    #[fail(display = "No acceptable encoding could be negotiated.")]
+    NoAcceptableEncoding,
+}
+
+impl Fail for Error {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl Error {
+    /// Returns the [`ErrorKind`] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        *self.inner.get_context()
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error {
+            inner: Context::new(kind),
+        }
+    }
+}
+
+impl From<Context<ErrorKind>> for Error {
+    fn from(inner: Context<ErrorKind>) -> Error {
+        Error { inner }
+    }
+}
@@ -37,7 +37,8 @@ mod error;
 
 pub use crate::error::{Error, ErrorKind, Result};
 use failure::ResultExt;
-use http::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING};
+use http::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, TE};
+use std::cmp::Ordering;
 
 /// Encodings to use.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -52,12 +53,19 @@ pub enum Encoding {
     Zstd,
     /// No encoding.
     Identity,
+    /// The chunked transfer coding.
+    ///
+    /// This is only meaningful for the `TE` request header; it is never a valid
+    /// content coding for `Accept-Encoding`/`Content-Encoding` negotiation.
+    Chunked,
 }
 
 impl Encoding {
     /// Parses a given string into its corresponding encoding.
+    ///
+    /// Content-coding tokens are case-insensitive, so `GZIP` and `gzip` parse alike.
     fn parse(s: &str) -> Result<Option<Encoding>> {
-        match s {
+        match s.to_ascii_lowercase().as_str() {
             "gzip" => Ok(Some(Encoding::Gzip)),
             "deflate" => Ok(Some(Encoding::Deflate)),
             "br" => Ok(Some(Encoding::Brotli)),
@@ -68,39 +76,199 @@ impl Encoding {
         }
     }
 
+    /// Parses a token, additionally recognizing `chunked` when reading a `TE` header.
+    fn parse_with_te(s: &str, allow_chunked: bool) -> Result<Option<Encoding>> {
+        if allow_chunked && s.eq_ignore_ascii_case("chunked") {
+            return Ok(Some(Encoding::Chunked));
+        }
+        Encoding::parse(s)
+    }
+
+    /// Returns the content-coding token for this encoding.
+    fn token(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Identity => "identity",
+            Encoding::Chunked => "chunked",
+        }
+    }
+
     /// Converts the encoding into its' corresponding header value.
+    ///
+    /// Note that [`Encoding::Chunked`] is only valid in a `TE` header; it should never be used
+    /// to build an `Accept-Encoding`/`Content-Encoding` value.
     pub fn to_header_value(self) -> HeaderValue {
-        match self {
-            Encoding::Gzip => HeaderValue::from_str("gzip").unwrap(),
-            Encoding::Deflate => HeaderValue::from_str("deflate").unwrap(),
-            Encoding::Brotli => HeaderValue::from_str("br").unwrap(),
-            Encoding::Zstd => HeaderValue::from_str("zstd").unwrap(),
-            Encoding::Identity => HeaderValue::from_str("identity").unwrap(),
+        HeaderValue::from_str(self.token()).unwrap()
+    }
+}
+
+/// A single weighted encoding as proposed in an `Accept-Encoding` (or `TE`) header.
+///
+/// Unlike a bare `(Option<Encoding>, f32)` tuple, the weight is validated to lie in `[0.0, 1.0]`
+/// on construction. A weight of `None` means the client left the `q` parameter unspecified, which
+/// is treated as `1.0` for ordering purposes.
+///
+/// Proposals order by weight, best first, so a sorted slice can be iterated in priority order
+/// without re-scanning for the maximum q-value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncodingProposal {
+    encoding: Option<Encoding>,
+    weight: Option<f32>,
+}
+
+impl EncodingProposal {
+    /// Creates a new proposal, validating that `weight` (if any) lies in `[0.0, 1.0]`.
+    pub fn new(encoding: Option<Encoding>, weight: Option<f32>) -> Result<Self> {
+        if let Some(weight) = weight {
+            if !(0.0..=1.0).contains(&weight) {
+                return Err(Error::from(ErrorKind::InvalidEncoding));
+            }
         }
+        Ok(Self { encoding, weight })
+    }
+
+    /// The proposed encoding, or `None` for the `*` wildcard.
+    pub fn encoding(self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// The proposed weight, or `None` if the `q` parameter was unspecified.
+    pub fn weight(self) -> Option<f32> {
+        self.weight
+    }
+
+    /// Orders two proposals by weight, best first, treating an unspecified weight as `1.0`.
+    ///
+    /// This is a separate method rather than a `PartialOrd` impl: [`PartialEq`]/`==` compares
+    /// every field, so an ordering that calls two different codings of equal weight `Equal`
+    /// would disagree with `==`.
+    pub fn by_weight(&self, other: &Self) -> Ordering {
+        let this = self.weight.unwrap_or(1.0);
+        let other = other.weight.unwrap_or(1.0);
+        other.partial_cmp(&this).unwrap_or(Ordering::Equal)
     }
 }
 
-/// Parse a set of HTTP headers into a single option yielding an `Encoding` that the client prefers.
+/// A client's most preferred encoding, distinguishing a concrete coding from a wildcard.
 ///
-/// If you're looking for an easy way to determine the best encoding for the client and support every [`Encoding`] listed, this is likely what you want.
+/// Where [`parse`] collapses "no header" and a winning `*` into `None`, this keeps them apart:
+/// a [`Preference::Specific`] names a concrete [`Encoding`], while [`Preference::Any`] signals
+/// that the client accepts any coding the server cares to pick.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Preference<T> {
+    /// The client expressed no specific preference (a `*` wildcard won).
+    Any,
+    /// The client most prefers a specific encoding.
+    Specific(T),
+}
+
+/// Parse a set of HTTP headers into the client's single most preferred encoding.
 ///
-/// Note that a result of `None` indicates there preference is expressed on which encoding to use.
-/// Either the `Accept-Encoding` header is not present, or `*` is set as the most preferred encoding.
-pub fn parse(headers: &HeaderMap) -> Result<Option<Encoding>> {
+/// Unlike [`parse`], this distinguishes the two cases that `parse` flattens into `None`: a result
+/// of `None` means the `Accept-Encoding` header was absent (or named no known coding with a
+/// non-zero weight), `Some(Preference::Any)` means a `*` wildcard was the top preference, and
+/// `Some(Preference::Specific(e))` names a concrete encoding.
+pub fn preference(headers: &HeaderMap) -> Result<Option<Preference<Encoding>>> {
     let mut preferred_encoding = None;
     let mut max_qval = 0.0;
 
-    for (encoding, qval) in encodings(headers)? {
+    for proposal in encodings(headers)? {
+        let encoding = proposal.encoding();
+        let qval = proposal.weight().unwrap_or(1.0);
         if (qval - 1.0f32).abs() < 0.01 {
-            preferred_encoding = encoding;
+            preferred_encoding = Some(encoding);
             break;
         } else if qval > max_qval {
-            preferred_encoding = encoding;
+            preferred_encoding = Some(encoding);
             max_qval = qval;
         }
     }
 
-    Ok(preferred_encoding)
+    Ok(preferred_encoding.map(|encoding| match encoding {
+        Some(encoding) => Preference::Specific(encoding),
+        None => Preference::Any,
+    }))
+}
+
+/// Parse a set of HTTP headers into a single option yielding an `Encoding` that the client prefers.
+///
+/// If you're looking for an easy way to determine the best encoding for the client and support every [`Encoding`] listed, this is likely what you want.
+///
+/// Note that a result of `None` indicates no preference is expressed on which encoding to use.
+/// Either the `Accept-Encoding` header is not present, or `*` is set as the most preferred encoding.
+pub fn parse(headers: &HeaderMap) -> Result<Option<Encoding>> {
+    Ok(match preference(headers)? {
+        Some(Preference::Specific(encoding)) => Some(encoding),
+        Some(Preference::Any) | None => None,
+    })
+}
+
+/// Determine the q-value the client assigns to a given encoding.
+///
+/// A specific token match always wins; failing that the `*` wildcard applies.
+/// If neither is present, `identity` is implicitly acceptable while every other
+/// coding is not, per [RFC 7231 §5.3.4](https://tools.ietf.org/html/rfc7231#section-5.3.4).
+///
+/// Returns `None` when the client expressed no opinion about `encoding` — it was neither listed
+/// explicitly nor covered by a `*` wildcard. Such an encoding is not part of the intersection and
+/// must not compete in the max-q comparison; `Identity`'s implicit acceptability is handled as a
+/// fallback in [`negotiate`] rather than synthesized here.
+fn qvalue(encoding: Encoding, accepted: &[EncodingProposal], wildcard: Option<f32>) -> Option<f32> {
+    if let Some(proposal) = accepted.iter().find(|p| p.encoding() == Some(encoding)) {
+        Some(proposal.weight().unwrap_or(1.0))
+    } else {
+        wildcard
+    }
+}
+
+/// Negotiate the best encoding the server can produce from the client's ranked preferences.
+///
+/// Unlike [`parse`], this intersects the client's `Accept-Encoding` list with the `supported`
+/// codings the consumer can actually emit and returns the highest-q member of that intersection,
+/// breaking ties in `supported` order. Encodings listed with `q=0` are explicitly forbidden and
+/// never chosen.
+///
+/// If the client expresses no usable preference (the header is absent, or only names codings this
+/// server does not support), `Identity` is returned as the safe default. However, if the client
+/// has forbidden the identity coding — via `identity;q=0` or `*;q=0` — and none of its acceptable
+/// codings are supported, an [`ErrorKind::NoAcceptableEncoding`] error is returned so the caller
+/// can respond with `406 Not Acceptable`.
+pub fn negotiate(headers: &HeaderMap, supported: &[Encoding]) -> Result<Option<Encoding>> {
+    let accepted = encodings(headers)?;
+    if accepted.is_empty() {
+        return Ok(Some(Encoding::Identity));
+    }
+
+    let wildcard = accepted
+        .iter()
+        .find(|p| p.encoding().is_none())
+        .map(|p| p.weight().unwrap_or(1.0));
+
+    let mut best: Option<(Encoding, f32)> = None;
+    for &encoding in supported {
+        if let Some(qval) = qvalue(encoding, &accepted, wildcard) {
+            if qval > 0.0 && best.is_none_or(|(_, bq)| qval > bq) {
+                best = Some((encoding, qval));
+            }
+        }
+    }
+
+    if let Some((encoding, _)) = best {
+        return Ok(Some(encoding));
+    }
+
+    let identity_forbidden = wildcard == Some(0.0)
+        || accepted
+            .iter()
+            .any(|p| p.encoding() == Some(Encoding::Identity) && p.weight() == Some(0.0));
+    if identity_forbidden {
+        Err(ErrorKind::NoAcceptableEncoding)?
+    } else {
+        Ok(Some(Encoding::Identity))
+    }
 }
 
 /// Parse a set of HTTP headers into a vector containing tuples of options containing encodings and their corresponding q-values.
@@ -120,14 +288,59 @@ pub fn parse(headers: &HeaderMap) -> Result<Option<Encoding>> {
 /// headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("zstd;q=1.0, deflate;q=0.8, br;q=0.9")?);
 ///
 /// let encodings = accept_encoding::encodings(&headers)?;
-/// for (encoding, qval) in encodings {
-///     println!("{:?} {}", encoding, qval);
+/// for proposal in encodings {
+///     println!("{:?} {:?}", proposal.encoding(), proposal.weight());
 /// }
 /// # Ok(())}
 /// ```
-pub fn encodings(headers: &HeaderMap) -> Result<Vec<(Option<Encoding>, f32)>> {
+pub fn encodings(headers: &HeaderMap) -> Result<Vec<EncodingProposal>> {
+    let mut proposals = parse_encodings(headers, ACCEPT_ENCODING, false)?;
+    // Sort best-first by weight; the sort is stable, so equal weights keep header order.
+    proposals.sort_by(EncodingProposal::by_weight);
+    Ok(proposals)
+}
+
+/// Parse a set of HTTP headers into the single transfer coding the client most prefers via `TE`.
+///
+/// This is the `TE`-header counterpart of [`parse`]. Note that `chunked` is always implicitly
+/// acceptable over HTTP/1.1 and so is recognized here as [`Encoding::Chunked`].
+pub fn parse_te(headers: &HeaderMap) -> Result<Option<Encoding>> {
+    let mut preferred_encoding = None;
+    let mut max_qval = 0.0;
+
+    for proposal in te_encodings(headers)? {
+        let encoding = proposal.encoding();
+        let qval = proposal.weight().unwrap_or(1.0);
+        if (qval - 1.0f32).abs() < 0.01 {
+            preferred_encoding = encoding;
+            break;
+        } else if qval > max_qval {
+            preferred_encoding = encoding;
+            max_qval = qval;
+        }
+    }
+
+    Ok(preferred_encoding)
+}
+
+/// Parse the `TE` request header into a vector of transfer codings and their weights.
+///
+/// This is the `TE`-header counterpart of [`encodings`], returned in header order. Unlike
+/// `Accept-Encoding`, the `TE` header additionally accepts the `chunked` transfer coding,
+/// surfaced here as [`Encoding::Chunked`].
+pub fn te_encodings(headers: &HeaderMap) -> Result<Vec<EncodingProposal>> {
+    parse_encodings(headers, TE, true)
+}
+
+/// Parse a named header into a vector of proposals, shared by `Accept-Encoding` and `TE`.
+/// When `allow_chunked` is set, the `chunked` token is recognized. Results are in header order.
+fn parse_encodings(
+    headers: &HeaderMap,
+    header: HeaderName,
+    allow_chunked: bool,
+) -> Result<Vec<EncodingProposal>> {
     headers
-        .get_all(ACCEPT_ENCODING)
+        .get_all(header)
         .iter()
         .map(|hval| {
             hval.to_str()
@@ -138,25 +351,100 @@ pub fn encodings(headers: &HeaderMap) -> Result<Vec<(Option<Encoding>, f32)>> {
         .iter()
         .flat_map(|s| s.split(',').map(str::trim))
         .filter_map(|v| {
-            let mut v = v.splitn(2, ";q=");
-            let encoding = match Encoding::parse(v.next().unwrap()) {
+            if v.is_empty() {
+                return None; // skip empty elements rather than erroring
+            }
+
+            // Split the content-coding token from its (optional) parameters.
+            let mut parts = v.split(';');
+            let token = parts.next().unwrap().trim();
+            if token.is_empty() {
+                return None;
+            }
+            let encoding = match Encoding::parse_with_te(token, allow_chunked) {
                 Ok(encoding) => encoding,
                 Err(_) => return None, // ignore unknown encodings
             };
-            let qval = if let Some(qval) = v.next() {
-                let qval = match qval.parse::<f32>() {
+
+            // Scan the parameters for a `q`/`Q` weight, tolerating surrounding whitespace.
+            // A missing weight stays `None` ("unspecified").
+            let mut weight = None;
+            for param in parts {
+                let mut kv = param.splitn(2, '=');
+                let name = kv.next().unwrap().trim();
+                if !name.eq_ignore_ascii_case("q") {
+                    continue;
+                }
+                let raw = match kv.next() {
+                    Some(raw) => raw.trim(),
+                    None => return Some(Err(ErrorKind::InvalidEncoding)),
+                };
+                // A q-value carries at most three decimal places.
+                let mut digits = raw.splitn(2, '.');
+                digits.next();
+                if let Some(frac) = digits.next() {
+                    if frac.len() > 3 {
+                        return Some(Err(ErrorKind::InvalidEncoding));
+                    }
+                }
+                let qval = match raw.parse::<f32>() {
                     Ok(f) => f,
                     Err(_) => return Some(Err(ErrorKind::InvalidEncoding)),
                 };
-                if qval > 1.0 {
-                    return Some(Err(ErrorKind::InvalidEncoding)); // q-values over 1 are unacceptable
+                if !(0.0..=1.0).contains(&qval) {
+                    return Some(Err(ErrorKind::InvalidEncoding)); // q-values live in [0.0, 1.0]
                 }
-                qval
-            } else {
-                1.0f32
-            };
-            Some(Ok((encoding, qval)))
+                weight = Some(qval);
+                break;
+            }
+            Some(Ok(EncodingProposal { encoding, weight }))
         })
         .map(|v| v.map_err(std::convert::Into::into))
-        .collect::<Result<Vec<(Option<Encoding>, f32)>>>()
+        .collect::<Result<Vec<EncodingProposal>>>()
+}
+
+/// Serialize a weighted list of encodings into an `Accept-Encoding` header value.
+///
+/// This is the inverse of [`encodings`]: it renders a value such as
+/// `zstd;q=1, br;q=0.9, gzip;q=0.5`, in the order given. A `None` weight omits the `;q=`
+/// parameter entirely, and weights are formatted with up to three decimal places and no trailing
+/// zeros. Each weight is validated to lie in `[0.0, 1.0]`.
+///
+/// ## Examples
+/// ```rust
+/// # use failure::Error;
+/// use accept_encoding::Encoding;
+///
+/// # fn main () -> Result<(), failure::Error> {
+/// let value = accept_encoding::to_header_value(&[
+///     (Encoding::Zstd, Some(1.0)),
+///     (Encoding::Brotli, Some(0.9)),
+///     (Encoding::Gzip, None),
+/// ])?;
+/// assert_eq!(value.to_str()?, "zstd;q=1, br;q=0.9, gzip");
+/// # Ok(())}
+/// ```
+pub fn to_header_value(proposals: &[(Encoding, Option<f32>)]) -> Result<HeaderValue> {
+    let mut value = String::new();
+    for &(encoding, weight) in proposals {
+        if !value.is_empty() {
+            value.push_str(", ");
+        }
+        value.push_str(encoding.token());
+        if let Some(weight) = weight {
+            // Reuse the proposal constructor to validate the weight range.
+            EncodingProposal::new(Some(encoding), Some(weight))?;
+            value.push_str(";q=");
+            value.push_str(&format_weight(weight));
+        }
+    }
+    HeaderValue::from_str(&value)
+        .context(ErrorKind::InvalidEncoding)
+        .map_err(Into::into)
+}
+
+/// Formats a weight with at most three decimal places and no trailing zeros.
+fn format_weight(weight: f32) -> String {
+    let formatted = format!("{:.3}", weight);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
 }
@@ -80,6 +80,166 @@ fn multiple_encodings_with_qval_3() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn build_header_value() -> Result<(), Error> {
+    let value = accept_encoding::to_header_value(&[
+        (Encoding::Zstd, Some(1.0)),
+        (Encoding::Brotli, Some(0.9)),
+        (Encoding::Gzip, Some(0.5)),
+    ])?;
+    assert_eq!(value.to_str()?, "zstd;q=1, br;q=0.9, gzip;q=0.5");
+
+    let value = accept_encoding::to_header_value(&[(Encoding::Gzip, None)])?;
+    assert_eq!(value.to_str()?, "gzip");
+
+    assert!(accept_encoding::to_header_value(&[(Encoding::Gzip, Some(1.5))]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn encoding_proposal_validates_weight() -> Result<(), Error> {
+    use accept_encoding::EncodingProposal;
+
+    assert!(EncodingProposal::new(Some(Encoding::Gzip), Some(0.5)).is_ok());
+    assert!(EncodingProposal::new(Some(Encoding::Gzip), None).is_ok());
+    assert!(EncodingProposal::new(Some(Encoding::Gzip), Some(1.5)).is_err());
+    assert!(EncodingProposal::new(Some(Encoding::Gzip), Some(-0.1)).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn encodings_sorted_best_first() -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        ACCEPT_ENCODING,
+        HeaderValue::from_str("gzip;q=0.5, br;q=1.0, zstd;q=0.8")?,
+    );
+
+    let encodings = accept_encoding::encodings(&headers)?;
+    assert_eq!(encodings[0].encoding(), Some(Encoding::Brotli));
+    assert_eq!(encodings[1].encoding(), Some(Encoding::Zstd));
+    assert_eq!(encodings[2].encoding(), Some(Encoding::Gzip));
+
+    Ok(())
+}
+
+#[test]
+fn preference_distinguishes_wildcard_from_absent() -> Result<(), Error> {
+    use accept_encoding::Preference;
+
+    let headers = HeaderMap::new();
+    assert_eq!(accept_encoding::preference(&headers)?, None);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("*")?);
+    assert_eq!(
+        accept_encoding::preference(&headers)?,
+        Some(Preference::Any)
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("gzip, br;q=0.5")?);
+    assert_eq!(
+        accept_encoding::preference(&headers)?,
+        Some(Preference::Specific(Encoding::Gzip))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn negotiate_picks_best_supported() -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        ACCEPT_ENCODING,
+        HeaderValue::from_str("gzip;q=0.5, br;q=1.0, zstd;q=0.8")?,
+    );
+
+    let encoding = accept_encoding::negotiate(&headers, &[Encoding::Gzip, Encoding::Zstd])?.unwrap();
+    assert_eq!(encoding, Encoding::Zstd);
+
+    Ok(())
+}
+
+#[test]
+fn negotiate_absent_header_defaults_to_identity() -> Result<(), Error> {
+    let headers = HeaderMap::new();
+
+    let encoding = accept_encoding::negotiate(&headers, &[Encoding::Gzip])?.unwrap();
+    assert_eq!(encoding, Encoding::Identity);
+
+    Ok(())
+}
+
+#[test]
+fn negotiate_unlisted_identity_does_not_beat_listed() -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("gzip;q=0.5")?);
+
+    let encoding =
+        accept_encoding::negotiate(&headers, &[Encoding::Gzip, Encoding::Identity])?.unwrap();
+    assert_eq!(encoding, Encoding::Gzip);
+
+    Ok(())
+}
+
+#[test]
+fn negotiate_forbidden_qval_is_excluded() -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("gzip;q=0, br;q=1.0")?);
+
+    let encoding = accept_encoding::negotiate(&headers, &[Encoding::Gzip, Encoding::Brotli])?.unwrap();
+    assert_eq!(encoding, Encoding::Brotli);
+
+    Ok(())
+}
+
+#[test]
+fn negotiate_no_acceptable_encoding() -> Result<(), Error> {
+    use accept_encoding::ErrorKind;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("gzip, *;q=0")?);
+
+    let err = accept_encoding::negotiate(&headers, &[Encoding::Brotli]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NoAcceptableEncoding);
+
+    Ok(())
+}
+
+#[test]
+fn parse_te_recognizes_chunked() -> Result<(), Error> {
+    use http::header::TE;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(TE, HeaderValue::from_str("chunked;q=0.5, gzip;q=1.0")?);
+
+    let encoding = accept_encoding::parse_te(&headers)?.unwrap();
+    assert_eq!(encoding, Encoding::Gzip);
+
+    let te = accept_encoding::te_encodings(&headers)?;
+    assert_eq!(te[0].encoding(), Some(Encoding::Chunked));
+    assert_eq!(te[0].weight(), Some(0.5));
+    assert_eq!(te[1].encoding(), Some(Encoding::Gzip));
+    assert_eq!(te[1].weight(), Some(1.0));
+
+    Ok(())
+}
+
+#[test]
+fn chunked_not_recognized_in_accept_encoding() -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("chunked, gzip")?);
+
+    let encodings = accept_encoding::encodings(&headers)?;
+    assert_eq!(encodings.len(), 1);
+    assert_eq!(encodings[0].encoding(), Some(Encoding::Gzip));
+
+    Ok(())
+}
+
 #[test]
 fn list_encodings() -> Result<(), Error> {
     use accept_encoding::Encoding;
@@ -91,9 +251,37 @@ fn list_encodings() -> Result<(), Error> {
     );
 
     let encodings = accept_encoding::encodings(&headers)?;
-    assert_eq!(encodings[0], (Some(Encoding::Zstd), 1.0));
-    assert_eq!(encodings[1], (Some(Encoding::Deflate), 0.8));
-    assert_eq!(encodings[2], (Some(Encoding::Brotli), 0.9));
+    // Sorted best-first by weight: zstd (1.0), br (0.9), deflate (0.8).
+    assert_eq!(encodings[0].encoding(), Some(Encoding::Zstd));
+    assert_eq!(encodings[1].encoding(), Some(Encoding::Brotli));
+    assert_eq!(encodings[2].encoding(), Some(Encoding::Deflate));
+    Ok(())
+}
+
+#[test]
+fn parse_tolerates_whitespace_and_case() -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        ACCEPT_ENCODING,
+        HeaderValue::from_str("GZIP; q=0.5, BR; Q =1.0")?,
+    );
+
+    let encoding = accept_encoding::parse(&headers)?.unwrap();
+    assert_eq!(encoding, Encoding::Brotli);
+
+    Ok(())
+}
+
+#[test]
+fn parse_rejects_out_of_range_qval() -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("gzip;q=1.001")?);
+    assert!(accept_encoding::parse(&headers).is_err());
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_str("gzip;q=-0.1")?);
+    assert!(accept_encoding::parse(&headers).is_err());
+
     Ok(())
 }
 
@@ -108,7 +296,7 @@ fn list_encodings_ignore_unknown() -> Result<(), Error> {
     );
 
     let encodings = accept_encoding::encodings(&headers)?;
-    assert_eq!(encodings[0], (Some(Encoding::Zstd), 1.0));
-    assert_eq!(encodings[1], (Some(Encoding::Brotli), 0.9));
+    assert_eq!(encodings[0].encoding(), Some(Encoding::Zstd));
+    assert_eq!(encodings[1].encoding(), Some(Encoding::Brotli));
     Ok(())
 }